@@ -0,0 +1,47 @@
+//! Admin HTTP listener, separate from the gRPC port: Prometheus `/metrics`
+//! and a `/health` readiness probe. Kept intentionally tiny — no auth, no
+//! TLS — since it's meant to live behind cluster-internal scraping only.
+
+use crate::metrics;
+use crate::stockfish_manager::StockfishManager;
+use axum::{extract::State, http::StatusCode, routing::get, Router};
+use deadpool::managed::Pool;
+use std::net::SocketAddr;
+
+#[derive(Clone)]
+struct AdminState {
+    pool: Pool<StockfishManager>,
+}
+
+pub async fn serve(pool: Pool<StockfishManager>, addr: SocketAddr) -> Result<(), std::io::Error> {
+    let state = AdminState { pool };
+    let app = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .route("/health", get(health_handler))
+        .with_state(state);
+
+    println!("📊 Admin listener (metrics/health) on {}", addr);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await
+}
+
+async fn metrics_handler(State(state): State<AdminState>) -> String {
+    let status = state.pool.status();
+
+    metrics::POOL_SIZE.set(status.size as i64);
+    metrics::POOL_AVAILABLE.set(status.available as i64);
+    metrics::POOL_IN_USE.set(status.size as i64 - status.available as i64);
+    metrics::POOL_WAITING.set(status.waiting as i64);
+
+    metrics::encode()
+}
+
+async fn health_handler(State(state): State<AdminState>) -> (StatusCode, &'static str) {
+    // Ready as long as the pool is configured to hold at least one
+    // instance; a pool with max_size 0 would never serve a request.
+    if state.pool.status().max_size > 0 {
+        (StatusCode::OK, "OK")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "UNHEALTHY")
+    }
+}
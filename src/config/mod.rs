@@ -1,9 +1,49 @@
 use std::env;
 
+/// Which transport the binary serves moves over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunMode {
+    /// Default: the gRPC `ChessBot` service.
+    Grpc,
+    /// Logs into Lichess as a bot account and plays accepted challenges.
+    Lichess,
+}
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub server_host: String,
     pub server_port: u16,
+    pub run_mode: RunMode,
+    pub lichess: LichessConfig,
+    /// Host/port for the admin HTTP listener (`/metrics`, `/health`),
+    /// separate from the gRPC port so scraping never competes with traffic.
+    pub admin_host: String,
+    pub admin_port: u16,
+    pub engine_tuning: EngineTuning,
+}
+
+/// Per-instance UCI options applied once to every pooled Stockfish process.
+/// The pool already sizes to `num_cpus::get()`, so giving each engine more
+/// than one thread oversubscribes the CPU unless the deployment deliberately
+/// trades concurrency for single-analysis strength.
+#[derive(Clone, Copy, Debug)]
+pub struct EngineTuning {
+    pub threads: u32,
+    pub hash_mb: u32,
+}
+
+/// Challenge filtering and auth for the Lichess bot (`RunMode::Lichess`).
+#[derive(Clone, Debug)]
+pub struct LichessConfig {
+    pub token: Option<String>,
+    pub accept_rated: bool,
+    pub accept_casual: bool,
+    /// Lichess speed categories to accept (e.g. "bullet", "blitz", "rapid",
+    /// "classical", "correspondence"). Empty means accept any speed.
+    pub allowed_speeds: Vec<String>,
+    /// Variants to accept (e.g. "standard", "chess960"). Empty means
+    /// standard chess only.
+    pub allowed_variants: Vec<String>,
 }
 
 impl Config {
@@ -14,10 +54,52 @@ impl Config {
                 .unwrap_or_else(|_| "50051".to_string())
                 .parse()
                 .unwrap_or(50051),
+            run_mode: match env::var("RUN_MODE").unwrap_or_default().as_str() {
+                "lichess" => RunMode::Lichess,
+                _ => RunMode::Grpc,
+            },
+            lichess: LichessConfig {
+                token: env::var("LICHESS_TOKEN").ok(),
+                accept_rated: env_flag("LICHESS_ACCEPT_RATED", true),
+                accept_casual: env_flag("LICHESS_ACCEPT_CASUAL", true),
+                allowed_speeds: env_list("LICHESS_ALLOWED_SPEEDS"),
+                allowed_variants: env_list("LICHESS_ALLOWED_VARIANTS"),
+            },
+            admin_host: env::var("ADMIN_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+            admin_port: env::var("ADMIN_PORT")
+                .unwrap_or_else(|_| "9090".to_string())
+                .parse()
+                .unwrap_or(9090),
+            engine_tuning: EngineTuning {
+                threads: env_parsed("ENGINE_THREADS", 1),
+                hash_mb: env_parsed("ENGINE_HASH_MB", 16),
+            },
         })
     }
 
     pub fn server_address(&self) -> String {
         format!("{}:{}", self.server_host, self.server_port)
     }
+
+    pub fn admin_address(&self) -> String {
+        format!("{}:{}", self.admin_host, self.admin_port)
+    }
+}
+
+fn env_flag(name: &str, default: bool) -> bool {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+fn env_parsed<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+fn env_list(name: &str) -> Vec<String> {
+    env::var(name)
+        .ok()
+        .map(|v| v.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default()
 }
@@ -0,0 +1,348 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// One line of a MultiPV search, as reported by a single `info ... multipv K ...`
+/// update from Stockfish.
+#[derive(Clone, Debug)]
+pub struct PvLine {
+    /// 1-based MultiPV rank.
+    pub multipv: u32,
+    pub depth: u32,
+    pub seldepth: u32,
+    pub nodes: u64,
+    pub nps: u64,
+    pub time_ms: u64,
+    pub score_cp: Option<i32>,
+    pub mate_in: Option<i32>,
+    /// Principal variation, UCI moves in order.
+    pub pv: Vec<String>,
+}
+
+/// Raw UCI process wrapper around a Stockfish binary.
+///
+/// `StockfishManager` pools these; unlike the single `best_move()`/`eval()`
+/// the old wrapper crate exposed, this one talks UCI directly so callers can
+/// read multiple `info` lines per search (MultiPV, live search updates).
+pub struct StockfishEngine {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl StockfishEngine {
+    pub fn new(path: &str) -> io::Result<Self> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdin = child.stdin.take().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::BrokenPipe, "Stockfish stdin not piped")
+        })?;
+        let stdout = child.stdout.take().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::BrokenPipe, "Stockfish stdout not piped")
+        })?;
+
+        let mut engine = Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        };
+
+        engine.send("uci")?;
+        engine.wait_for("uciok")?;
+        Ok(engine)
+    }
+
+    pub fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.send(&format!("setoption name {} value {}", name, value))
+    }
+
+    pub fn set_fen_position(&mut self, fen: &str) -> io::Result<()> {
+        self.send(&format!("position fen {}", fen))
+    }
+
+    pub fn setup_for_new_game(&mut self) -> io::Result<()> {
+        self.send("ucinewgame")?;
+        self.send("isready")?;
+        self.wait_for("readyok")
+    }
+
+    pub fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        let bytes = self.stdout.read_line(&mut line)?;
+        if bytes == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Stockfish process closed its stdout",
+            ));
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    fn wait_for(&mut self, token: &str) -> io::Result<()> {
+        loop {
+            if self.read_line()?.contains(token) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Runs `go depth <depth>` with `MultiPV value 1` and returns the single
+    /// best line found. Used by `GetBestMove`, which only needs the top move.
+    pub fn go_best_move(&mut self, depth: u32) -> io::Result<PvLine> {
+        self.go_stream(&format!("depth {}", depth), 1, |_| {})
+    }
+
+    /// Runs `go depth <depth>` with `MultiPV` set to `multi_pv`, returning the
+    /// PV lines reported at the last fully-searched depth, ranked 1..=multi_pv.
+    pub fn go_multipv(&mut self, depth: u32, multi_pv: u32) -> io::Result<Vec<PvLine>> {
+        self.go(&format!("depth {}", depth), multi_pv)
+    }
+
+    /// Runs `go movetime <movetime_ms>`, letting the engine search at full
+    /// depth for a fixed wall-clock budget instead of a depth cap. Used for
+    /// `UCI_Elo`-limited play, where capping depth would make the engine look
+    /// artificially blind rather than humanly weak.
+    pub fn go_movetime(&mut self, movetime_ms: u32) -> io::Result<PvLine> {
+        self.go_stream(&format!("movetime {}", movetime_ms), 1, |_| {})
+    }
+
+    /// Runs `go wtime ... btime ... winc ... binc ...`, letting the engine
+    /// allocate its own thinking time from the game clock instead of a
+    /// fixed depth or movetime budget.
+    pub fn go_clock(
+        &mut self,
+        wtime_ms: u32,
+        btime_ms: u32,
+        winc_ms: u32,
+        binc_ms: u32,
+        moves_to_go: Option<u32>,
+    ) -> io::Result<PvLine> {
+        let mut args = format!(
+            "wtime {} btime {} winc {} binc {}",
+            wtime_ms, btime_ms, winc_ms, binc_ms
+        );
+        if let Some(moves_to_go) = moves_to_go {
+            args.push_str(&format!(" movestogo {}", moves_to_go));
+        }
+
+        self.go_stream(&args, 1, |_| {})
+    }
+
+    /// Sends `go <go_args>` with `MultiPV` set to `multi_pv` and collects the
+    /// PV lines reported at the last fully-searched depth, ranked 1..=multi_pv.
+    ///
+    /// A position with fewer legal moves than `multi_pv` never fills every
+    /// rank (e.g. `multi_pv=5` in a position with three legal moves), so this
+    /// returns whatever ranks Stockfish actually reported rather than
+    /// requiring all `multi_pv` slots to be populated.
+    fn go(&mut self, go_args: &str, multi_pv: u32) -> io::Result<Vec<PvLine>> {
+        let mut current: Vec<Option<PvLine>> = vec![None; multi_pv as usize];
+
+        self.go_stream(go_args, multi_pv, |pv| {
+            let idx = (pv.multipv.saturating_sub(1)) as usize;
+            if idx < current.len() {
+                current[idx] = Some(pv.clone());
+            }
+        })?;
+
+        let lines: Vec<PvLine> = current.into_iter().flatten().collect();
+        if lines.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Stockfish never reported a PV line",
+            ));
+        }
+        Ok(lines)
+    }
+
+    /// Sends `go <go_args>` with `MultiPV` set to `multi_pv`, invoking
+    /// `on_update` for every `info` line as it arrives (not just the final
+    /// completed depth) and returning the last rank-1 line for the final
+    /// `bestmove`. When `bestmove` differs from that line's first move (as
+    /// `Skill Level`/`UCI_LimitStrength` can make it), the returned PV is just
+    /// the `bestmove`, with no score. Used both by the blocking helpers above and by
+    /// `stream_analysis`, which forwards each `on_update` call straight to a
+    /// client over a live gRPC stream.
+    pub fn go_stream(
+        &mut self,
+        go_args: &str,
+        multi_pv: u32,
+        mut on_update: impl FnMut(&PvLine),
+    ) -> io::Result<PvLine> {
+        self.set_option("MultiPV", &multi_pv.to_string())?;
+        self.send(&format!("go {}", go_args))?;
+
+        let mut last_rank1: Option<PvLine> = None;
+        let mut best_move: Option<String> = None;
+
+        loop {
+            let line = self.read_line()?;
+            if line.starts_with("bestmove") {
+                best_move = line.split_whitespace().nth(1).map(|s| s.to_string());
+                break;
+            }
+            if let Some(pv) = parse_info_line(&line) {
+                if pv.multipv == 1 {
+                    last_rank1 = Some(pv.clone());
+                }
+                on_update(&pv);
+            }
+        }
+
+        let mut best = last_rank1.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Stockfish never reported a PV line before bestmove",
+            )
+        })?;
+        if let Some(best_move) = best_move {
+            if best.pv.first() != Some(&best_move) {
+                best.pv = vec![best_move];
+                best.score_cp = None;
+                best.mate_in = None;
+            }
+        }
+        Ok(best)
+    }
+}
+
+impl Drop for StockfishEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+    }
+}
+
+/// Parses a `info ... multipv K ... score (cp N | mate N) ... pv ...` line.
+/// Returns `None` for `info` lines that don't carry a MultiPV score/PV (e.g.
+/// `info string ...`).
+fn parse_info_line(line: &str) -> Option<PvLine> {
+    if !line.starts_with("info") {
+        return None;
+    }
+
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let mut depth = 0u32;
+    let mut seldepth = 0u32;
+    let mut nodes = 0u64;
+    let mut nps = 0u64;
+    let mut time_ms = 0u64;
+    let mut multipv = 1u32;
+    let mut score_cp = None;
+    let mut mate_in = None;
+    let mut pv = Vec::new();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i] {
+            "depth" => {
+                depth = tokens.get(i + 1)?.parse().ok()?;
+                i += 2;
+            }
+            "seldepth" => {
+                seldepth = tokens.get(i + 1)?.parse().ok()?;
+                i += 2;
+            }
+            "nodes" => {
+                nodes = tokens.get(i + 1)?.parse().ok()?;
+                i += 2;
+            }
+            "nps" => {
+                nps = tokens.get(i + 1)?.parse().ok()?;
+                i += 2;
+            }
+            "time" => {
+                time_ms = tokens.get(i + 1)?.parse().ok()?;
+                i += 2;
+            }
+            "multipv" => {
+                multipv = tokens.get(i + 1)?.parse().ok()?;
+                i += 2;
+            }
+            "score" => {
+                match tokens.get(i + 1).copied() {
+                    Some("cp") => {
+                        score_cp = tokens.get(i + 2).and_then(|v| v.parse().ok());
+                    }
+                    Some("mate") => {
+                        mate_in = tokens.get(i + 2).and_then(|v| v.parse().ok());
+                    }
+                    _ => {}
+                }
+                i += 3;
+            }
+            "pv" => {
+                pv = tokens[i + 1..].iter().map(|s| s.to_string()).collect();
+                break;
+            }
+            _ => i += 1,
+        }
+    }
+
+    if pv.is_empty() {
+        return None;
+    }
+
+    Some(PvLine {
+        multipv,
+        depth,
+        seldepth,
+        nodes,
+        nps,
+        time_ms,
+        score_cp,
+        mate_in,
+        pv,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_cp_score_line() {
+        let line = "info depth 12 seldepth 16 multipv 1 score cp 34 nodes 123456 nps 789000 time 156 pv e2e4 e7e5 g1f3";
+        let pv = parse_info_line(line).expect("valid info line should parse");
+
+        assert_eq!(pv.depth, 12);
+        assert_eq!(pv.seldepth, 16);
+        assert_eq!(pv.multipv, 1);
+        assert_eq!(pv.score_cp, Some(34));
+        assert_eq!(pv.mate_in, None);
+        assert_eq!(pv.nodes, 123456);
+        assert_eq!(pv.nps, 789000);
+        assert_eq!(pv.time_ms, 156);
+        assert_eq!(pv.pv, vec!["e2e4", "e7e5", "g1f3"]);
+    }
+
+    #[test]
+    fn parses_a_mate_score_line() {
+        let line = "info depth 8 multipv 2 score mate 3 nodes 500 nps 10000 time 20 pv f7f5 g2g4";
+        let pv = parse_info_line(line).expect("valid info line should parse");
+
+        assert_eq!(pv.multipv, 2);
+        assert_eq!(pv.score_cp, None);
+        assert_eq!(pv.mate_in, Some(3));
+        assert_eq!(pv.pv, vec!["f7f5", "g2g4"]);
+    }
+
+    #[test]
+    fn rejects_non_info_lines() {
+        assert!(parse_info_line("bestmove e2e4 ponder e7e5").is_none());
+        assert!(parse_info_line("uciok").is_none());
+    }
+
+    #[test]
+    fn rejects_info_lines_without_a_pv() {
+        assert!(parse_info_line("info string NNUE evaluation enabled").is_none());
+    }
+}
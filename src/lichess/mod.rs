@@ -0,0 +1,351 @@
+//! Lichess Board/Bot API integration.
+//!
+//! Run with `RUN_MODE=lichess` instead of starting the gRPC server. Logs in
+//! with `LICHESS_TOKEN`, accepts challenges that pass `LichessConfig`'s
+//! filters, and plays accepted games by reconstructing the position from
+//! the `gameState` move list and asking the same pooled Stockfish engines
+//! the gRPC service uses.
+
+use crate::chess_bot::StrengthMode;
+use crate::config::LichessConfig;
+use crate::stockfish_manager::StockfishManager;
+use crate::strength;
+use deadpool::managed::Pool;
+use futures_util::StreamExt;
+use reqwest::Client;
+use shakmaty::{uci::UciMove, Chess, Position};
+use std::error::Error;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio_util::io::StreamReader;
+
+const LICHESS_API: &str = "https://lichess.org";
+
+/// Thin wrapper around the Lichess HTTP API, authenticated with a bot token.
+#[derive(Clone)]
+struct LichessClient {
+    http: Client,
+    token: String,
+}
+
+impl LichessClient {
+    fn new(token: String) -> Self {
+        Self {
+            http: Client::new(),
+            token,
+        }
+    }
+
+    /// Opens the account-wide event stream (`challenge`/`gameStart` events).
+    async fn stream_events(&self) -> Result<BufReader<impl tokio::io::AsyncRead>, Box<dyn Error>> {
+        self.stream_lines("/api/stream/event").await
+    }
+
+    /// Opens the per-game state stream (`gameFull` then `gameState` events).
+    async fn stream_game(&self, game_id: &str) -> Result<BufReader<impl tokio::io::AsyncRead>, Box<dyn Error>> {
+        self.stream_lines(&format!("/api/bot/game/stream/{}", game_id))
+            .await
+    }
+
+    async fn stream_lines(&self, path: &str) -> Result<BufReader<impl tokio::io::AsyncRead>, Box<dyn Error>> {
+        let resp = self
+            .http
+            .get(format!("{}{}", LICHESS_API, path))
+            .bearer_auth(&self.token)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let stream = resp
+            .bytes_stream()
+            .map(|r| r.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)));
+        Ok(BufReader::new(StreamReader::new(stream)))
+    }
+
+    async fn accept_challenge(&self, challenge_id: &str) -> Result<(), Box<dyn Error>> {
+        self.http
+            .post(format!("{}/api/challenge/{}/accept", LICHESS_API, challenge_id))
+            .bearer_auth(&self.token)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn decline_challenge(&self, challenge_id: &str) -> Result<(), Box<dyn Error>> {
+        self.http
+            .post(format!("{}/api/challenge/{}/decline", LICHESS_API, challenge_id))
+            .bearer_auth(&self.token)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn make_move(&self, game_id: &str, uci_move: &str) -> Result<(), Box<dyn Error>> {
+        self.http
+            .post(format!(
+                "{}/api/bot/game/{}/move/{}",
+                LICHESS_API, game_id, uci_move
+            ))
+            .bearer_auth(&self.token)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Fetches the bot's own account id, used to tell our color apart from
+    /// the opponent's in `gameFull` payloads.
+    async fn get_account_id(&self) -> Result<String, Box<dyn Error>> {
+        let account: serde_json::Value = self
+            .http
+            .get(format!("{}/api/account", LICHESS_API))
+            .bearer_auth(&self.token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        account["id"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| "Lichess account response missing \"id\"".into())
+    }
+}
+
+/// Starts the Lichess bot: accepts matching challenges and plays them out
+/// using `pool`, the same Stockfish pool the gRPC service draws from.
+pub async fn run(pool: Pool<StockfishManager>, lichess: &LichessConfig) -> Result<(), Box<dyn Error>> {
+    let token = lichess
+        .token
+        .clone()
+        .ok_or("LICHESS_TOKEN must be set to run in lichess mode")?;
+    let client = LichessClient::new(token);
+    let filters = lichess.clone();
+    let our_id = client.get_account_id().await?;
+
+    println!("♟️  Lichess bot connected as {}, waiting for challenges...", our_id);
+
+    let mut lines = client.stream_events().await?.lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("⚠️  Ignoring unparseable event: {}", e);
+                continue;
+            }
+        };
+
+        match event["type"].as_str() {
+            Some("challenge") => handle_challenge(&client, &event["challenge"], &filters).await,
+            Some("gameStart") => {
+                let Some(game_id) = event["game"]["id"].as_str() else {
+                    continue;
+                };
+                let game_id = game_id.to_string();
+                let client = client.clone();
+                let pool = pool.clone();
+                let our_id = our_id.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = play_game(&client, &pool, &game_id, &our_id).await {
+                        eprintln!("❌ Game {} ended with an error: {}", game_id, e);
+                    }
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_challenge(client: &LichessClient, challenge: &serde_json::Value, filters: &LichessConfig) {
+    let Some(id) = challenge["id"].as_str() else {
+        return;
+    };
+
+    if accepts_challenge(challenge, filters) {
+        println!("✅ Accepting challenge {}", id);
+        if let Err(e) = client.accept_challenge(id).await {
+            eprintln!("❌ Failed to accept challenge {}: {}", id, e);
+        }
+    } else {
+        println!("🚫 Declining challenge {}", id);
+        if let Err(e) = client.decline_challenge(id).await {
+            eprintln!("❌ Failed to decline challenge {}: {}", id, e);
+        }
+    }
+}
+
+fn accepts_challenge(challenge: &serde_json::Value, filters: &LichessConfig) -> bool {
+    let rated = challenge["rated"].as_bool().unwrap_or(false);
+    if rated && !filters.accept_rated {
+        return false;
+    }
+    if !rated && !filters.accept_casual {
+        return false;
+    }
+
+    // `allowed_variants` defaults to empty, which per `LichessConfig`'s doc
+    // comment means standard chess only — `replay_to_fen` only knows how to
+    // replay standard UCI moves onto a standard starting position, so
+    // accepting e.g. Chess960 by default would forfeit the game on replay.
+    let variant = challenge["variant"]["key"].as_str().unwrap_or("standard");
+    let variant_allowed = if filters.allowed_variants.is_empty() {
+        variant == "standard"
+    } else {
+        filters.allowed_variants.iter().any(|v| v == variant)
+    };
+    if !variant_allowed {
+        return false;
+    }
+
+    let speed = challenge["speed"].as_str().unwrap_or("");
+    if !filters.allowed_speeds.is_empty() && !filters.allowed_speeds.iter().any(|s| s == speed) {
+        return false;
+    }
+
+    true
+}
+
+/// Plays one accepted game end-to-end: streams `gameState` updates,
+/// replays the move list onto a fresh position to get the current FEN, and
+/// posts a move whenever it's our turn.
+async fn play_game(
+    client: &LichessClient,
+    pool: &Pool<StockfishManager>,
+    game_id: &str,
+    our_id: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut lines = client.stream_game(game_id).await?.lines();
+
+    let Some(first_line) = lines.next_line().await? else {
+        return Ok(());
+    };
+    let game_full: serde_json::Value = serde_json::from_str(&first_line)?;
+
+    let we_are_white = game_full["white"]["id"]
+        .as_str()
+        .is_some_and(|id| id.eq_ignore_ascii_case(our_id));
+
+    let opponent_elo = if we_are_white {
+        game_full["black"]["rating"].as_i64()
+    } else {
+        game_full["white"]["rating"].as_i64()
+    }
+    .unwrap_or(1500) as i32;
+
+    maybe_play_move(client, pool, game_id, &game_full["state"], we_are_white, opponent_elo).await?;
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: serde_json::Value = serde_json::from_str(&line)?;
+        match event["type"].as_str() {
+            Some("gameState") => {
+                if event["status"].as_str().is_some_and(|s| s != "started" && s != "created") {
+                    break;
+                }
+                maybe_play_move(client, pool, game_id, &event, we_are_white, opponent_elo).await?;
+            }
+            Some("gameFinish") => break,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// If the move list in `state` says it's our turn, picks a move and posts
+/// it back to Lichess.
+async fn maybe_play_move(
+    client: &LichessClient,
+    pool: &Pool<StockfishManager>,
+    game_id: &str,
+    state: &serde_json::Value,
+    we_are_white: bool,
+    opponent_elo: i32,
+) -> Result<(), Box<dyn Error>> {
+    let moves_str = state["moves"].as_str().unwrap_or("");
+    let moves: Vec<&str> = if moves_str.is_empty() {
+        Vec::new()
+    } else {
+        moves_str.split(' ').collect()
+    };
+
+    let white_to_move = moves.len() % 2 == 0;
+    if white_to_move != we_are_white {
+        return Ok(());
+    }
+
+    let fen = replay_to_fen(&moves)?;
+
+    let mut stockfish = pool.get().await?;
+    let strength = strength::resolve_strength(opponent_elo, StrengthMode::EloLimit);
+    let control = clock_from_state(state);
+
+    let best_move = tokio::task::spawn_blocking(move || {
+        strength::pick_move(&mut stockfish, &fen, &strength, &control)
+    })
+    .await??
+    .pv[0]
+        .clone();
+
+    client.make_move(game_id, &best_move).await?;
+    Ok(())
+}
+
+/// Builds a clock-aware `SearchControl` from a `gameState`'s `wtime`/`btime`
+/// /`winc`/`binc`, so the bot allocates its own thinking time instead of
+/// blitzing a fixed depth or timing out on a long time control.
+fn clock_from_state(state: &serde_json::Value) -> strength::SearchControl {
+    strength::SearchControl::Clock {
+        wtime_ms: state["wtime"].as_u64().unwrap_or(60_000) as u32,
+        btime_ms: state["btime"].as_u64().unwrap_or(60_000) as u32,
+        winc_ms: state["winc"].as_u64().unwrap_or(0) as u32,
+        binc_ms: state["binc"].as_u64().unwrap_or(0) as u32,
+        moves_to_go: None,
+    }
+}
+
+/// Replays a space-separated UCI move list onto the standard starting
+/// position and returns the resulting FEN.
+fn replay_to_fen(moves: &[&str]) -> Result<String, Box<dyn Error>> {
+    let mut pos = Chess::default();
+    for uci in moves {
+        let uci_move: UciMove = uci.parse()?;
+        let chess_move = uci_move.to_move(&pos)?;
+        pos = pos.play(chess_move)?;
+    }
+    Ok(shakmaty::fen::Fen::from_position(&pos, shakmaty::EnPassantMode::Legal).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_of_no_moves_is_the_starting_position() {
+        let fen = replay_to_fen(&[]).expect("empty move list replays");
+        assert_eq!(fen, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    }
+
+    #[test]
+    fn replay_applies_moves_in_order() {
+        let fen = replay_to_fen(&["e2e4", "e7e5", "g1f3"]).expect("legal moves replay");
+        assert_eq!(
+            fen,
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+    }
+
+    #[test]
+    fn replay_rejects_an_illegal_move() {
+        assert!(replay_to_fen(&["e2e5"]).is_err());
+    }
+}
@@ -1,9 +1,17 @@
 use dotenv::dotenv;
 use shakmaty::fen::Fen;
-use shakmaty::{san::San, uci::UciMove, Chess};
+use shakmaty::{san::San, uci::UciMove, Chess, Position};
+use std::pin::Pin;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
 use tonic::{transport::Server, Request, Response, Status};
+mod admin;
 mod config;
+mod engine;
+mod lichess;
+mod metrics;
 mod stockfish_manager;
+mod strength;
 use deadpool::managed::Pool;
 use std::net::SocketAddr;
 use stockfish_manager::StockfishManager;
@@ -13,7 +21,10 @@ pub mod chess_bot {
 }
 
 use chess_bot::chess_bot_server::{ChessBot, ChessBotServer};
-use chess_bot::{MoveResponse, PositionRequest};
+use chess_bot::{
+    AnalysisResponse, AnalysisUpdate, AnalyzePositionRequest, CandidateMove, MoveResponse,
+    PositionRequest,
+};
 use config::Config;
 
 #[derive(Clone)]
@@ -23,15 +34,20 @@ pub struct ChessBotService {
 
 #[tonic::async_trait]
 impl ChessBot for ChessBotService {
+    type StreamAnalysisStream =
+        Pin<Box<dyn Stream<Item = Result<AnalysisUpdate, Status>> + Send + 'static>>;
+
     async fn get_best_move(
         &self,
         request: Request<PositionRequest>,
     ) -> Result<Response<MoveResponse>, Status> {
         let req = request.into_inner();
+        metrics::GET_BEST_MOVE_REQUESTS.inc();
+        let _latency_timer = metrics::GET_BEST_MOVE_LATENCY.start_timer();
 
         println!(
             "📥 Received request: FEN={}, ELO={}",
-            &req.fen[..30],
+            req.fen.chars().take(30).collect::<String>(),
             req.elo_rating
         );
 
@@ -43,176 +59,332 @@ impl ChessBot for ChessBotService {
 
         println!("✅ Got Stockfish from pool");
 
-        let skill_level = calculate_skill_from_elo(req.elo_rating);
-        let depth = calculate_depth_from_elo(req.elo_rating);
-
-        println!("🎯 Skill level: {}, depth: {}", skill_level, depth);
+        let strength = strength::resolve_strength(req.elo_rating, req.strength_mode());
+        let control = search_control_from_request(&req, &strength);
+        println!("🎯 Strength: {:?}, control: {:?}", strength, control);
 
         // Виконуємо всі Stockfish операції в одному spawn_blocking
         let fen = req.fen.clone();
-        let result = tokio::task::spawn_blocking(move || {
-            // Налаштування skill level
+        let pv = tokio::task::spawn_blocking(move || {
+            strength::pick_move(&mut stockfish, &fen, &strength, &control)
+                .map_err(|e| format!("Engine error: {}", e))
+        })
+        .await
+        .map_err(|e| Status::internal(format!("Spawn error: {}", e)))?
+        .map_err(|e| {
+            metrics::ENGINE_ERRORS.inc();
+            Status::internal(e)
+        })?;
+
+        let uci_move_str = pv.pv[0].clone();
+        println!("✅ Got best move: {}", uci_move_str);
+
+        let decoded = decode_move(&req.fen, &uci_move_str)?;
+
+        println!("📤 Sending response: {}", decoded.san);
+
+        let response = MoveResponse {
+            best_move: uci_move_str,
+            score: pv.score_cp.unwrap_or(0),
+            from: decoded.from,
+            to: decoded.to,
+            piece: decoded.piece,
+            captured: decoded.captured,
+            promotion: decoded.promotion,
+            san: decoded.san,
+            mate_in: pv.mate_in.unwrap_or(0),
+            is_mate: pv.mate_in.is_some(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn analyze_position(
+        &self,
+        request: Request<AnalyzePositionRequest>,
+    ) -> Result<Response<AnalysisResponse>, Status> {
+        let req = request.into_inner();
+        let multi_pv = req.multi_pv.clamp(1, 5);
+
+        println!(
+            "📥 Received analyze request: FEN={}, MultiPV={}",
+            req.fen.chars().take(30).collect::<String>(),
+            multi_pv
+        );
+
+        let mut stockfish = self.pool.get().await.map_err(|e| {
+            eprintln!("❌ Failed to get Stockfish from pool: {}", e);
+            Status::internal("Pool exhausted")
+        })?;
+
+        let skill_level = strength::calculate_skill_from_elo(req.elo_rating);
+        let depth = strength::calculate_depth_from_elo(req.elo_rating);
+
+        let fen = req.fen.clone();
+        let lines = tokio::task::spawn_blocking(move || {
+            // A pooled engine may have last served a `GetBestMove` call in
+            // `EloLimit` mode, which leaves `UCI_LimitStrength`/`UCI_Elo` set;
+            // clear it so only the `elo_rating`-derived `Skill Level` applies.
             stockfish
-                .uci_send(&format!("setoption name Skill Level value {}", skill_level))
-                .map_err(|e| format!("Skill setup error: {}", e))?;
+                .set_option("UCI_LimitStrength", "false")
+                .map_err(|e| format!("Strength reset error: {}", e))?;
 
             stockfish
-                .uci_send("setoption name MultiPV value 1")
-                .map_err(|e| format!("MultiPV error: {}", e))?;
+                .set_option("Skill Level", &skill_level.to_string())
+                .map_err(|e| format!("Skill setup error: {}", e))?;
 
-            // Встановлення позиції
             stockfish
                 .set_fen_position(&fen)
                 .map_err(|e| format!("Invalid FEN: {}", e))?;
 
-            // Обчислення
-            stockfish.set_depth(depth as u32);
-            let engine_result = stockfish.go().map_err(|e| format!("Engine error: {}", e))?;
-
-            Ok::<_, String>(engine_result)
+            stockfish
+                .go_multipv(depth as u32, multi_pv)
+                .map_err(|e| format!("Engine error: {}", e))
         })
         .await
         .map_err(|e| Status::internal(format!("Spawn error: {}", e)))?
-        .map_err(|e| Status::internal(e))?;
-
-        println!("✅ Got best move: {}", result.best_move());
-
-        let uci_move_str = result.best_move().to_string();
+        .map_err(Status::internal)?;
+
+        let mut candidates = Vec::with_capacity(lines.len());
+        for line in lines {
+            let uci_move_str = line.pv[0].clone();
+            let decoded = decode_move(&req.fen, &uci_move_str)?;
+
+            candidates.push(CandidateMove {
+                rank: line.multipv,
+                uci_move: uci_move_str,
+                san: decoded.san,
+                score_cp: line.score_cp.unwrap_or(0),
+                mate_in: line.mate_in.unwrap_or(0),
+                is_mate: line.mate_in.is_some(),
+                pv: line.pv.join(" "),
+            });
+        }
+
+        println!("📤 Sending {} candidate line(s)", candidates.len());
+
+        Ok(Response::new(AnalysisResponse { candidates }))
+    }
 
-        // Парсинг FEN і створення move
-        let fen: Fen = req
-            .fen
-            .parse()
-            .map_err(|e| Status::invalid_argument(format!("Invalid FEN: {:?}", e)))?;
+    async fn stream_analysis(
+        &self,
+        request: Request<PositionRequest>,
+    ) -> Result<Response<Self::StreamAnalysisStream>, Status> {
+        let req = request.into_inner();
 
-        let pos: Chess = fen
-            .into_position(shakmaty::CastlingMode::Standard)
-            .map_err(|e| Status::invalid_argument(format!("Invalid position: {:?}", e)))?;
+        println!(
+            "📥 Received stream_analysis request: FEN={}",
+            req.fen.chars().take(30).collect::<String>()
+        );
 
-        let uci_move: UciMove = uci_move_str
-            .parse()
-            .map_err(|e| Status::internal(format!("Invalid UCI move: {:?}", e)))?;
+        let mut stockfish = self.pool.get().await.map_err(|e| {
+            eprintln!("❌ Failed to get Stockfish from pool: {}", e);
+            Status::internal("Pool exhausted")
+        })?;
 
-        let chess_move = uci_move
-            .to_move(&pos)
-            .map_err(|e| Status::internal(format!("Illegal move: {:?}", e)))?;
+        let (tx, rx) = tokio::sync::mpsc::channel(32);
 
-        let (from, to, piece, captured, promotion) = match &chess_move {
-            shakmaty::Move::Normal {
-                role,
-                from,
-                to,
-                capture,
-                promotion,
-            } => {
-                let piece_name = format!("{:?}", role);
-                let captured_name = capture.map(|c| format!("{:?}", c));
-                let promotion_name = promotion.map(|p| format!("{:?}", p));
-
-                (
-                    from.to_string(),
-                    to.to_string(),
-                    piece_name,
-                    captured_name,
-                    promotion_name,
-                )
+        let fen = req.fen.clone();
+        tokio::task::spawn_blocking(move || {
+            // Same stale-strength concern as `analyze_position`: a recycled
+            // engine may still have `UCI_LimitStrength`/`Skill Level` set
+            // from a prior `GetBestMove` call, since `ucinewgame` doesn't
+            // clear UCI options. Reset both to full strength.
+            if let Err(e) = stockfish.set_option("UCI_LimitStrength", "false") {
+                let _ = tx.blocking_send(Err(Status::internal(format!("Strength reset error: {}", e))));
+                return;
             }
-            shakmaty::Move::Castle { king, rook } => {
-                use shakmaty::{File, Square};
-
-                let king_to = if rook.file() == File::A {
-                    Square::from_coords(File::C, king.rank())
-                } else {
-                    Square::from_coords(File::G, king.rank())
-                };
-
-                (
-                    king.to_string(),
-                    king_to.to_string(),
-                    "King".to_string(),
-                    None,
-                    None,
-                )
+            if let Err(e) = stockfish.set_option("Skill Level", "20") {
+                let _ = tx.blocking_send(Err(Status::internal(format!("Strength reset error: {}", e))));
+                return;
             }
-            shakmaty::Move::EnPassant { from, to } => (
-                from.to_string(),
-                to.to_string(),
-                "Pawn".to_string(),
-                Some("Pawn".to_string()),
-                None,
-            ),
-            shakmaty::Move::Put { .. } => {
-                return Err(Status::internal("Put move not supported"));
+
+            if let Err(e) = stockfish.set_fen_position(&fen) {
+                let _ = tx.blocking_send(Err(Status::internal(format!("Invalid FEN: {}", e))));
+                return;
             }
-        };
 
-        let san = San::from_move(&pos, chess_move).to_string();
+            let search = stockfish.go_stream(&format!("depth {}", STREAM_ANALYSIS_DEPTH), 1, |pv| {
+                let _ = tx.blocking_send(Ok(pv_to_update(&fen, pv)));
+            });
 
-        println!("📤 Sending response: {}", san);
+            if let Err(e) = search {
+                let _ = tx.blocking_send(Err(Status::internal(format!("Engine error: {}", e))));
+            }
+        });
 
-        let response = MoveResponse {
-            best_move: uci_move_str,
-            score: result.eval().value(),
-            from,
-            to,
-            piece,
-            captured,
-            promotion,
-            san,
-        };
+        Ok(Response::new(
+            Box::pin(ReceiverStream::new(rx)) as Self::StreamAnalysisStream
+        ))
+    }
+}
 
-        Ok(Response::new(response))
+/// Search depth used for `StreamAnalysis`: deep enough to give a client a
+/// meaningful live eval bar, capped so the stream still terminates.
+const STREAM_ANALYSIS_DEPTH: u32 = 30;
+
+/// Turns one live `info` line into the `AnalysisUpdate` a `StreamAnalysis`
+/// client receives, rendering the PV as SAN on a best-effort basis (an
+/// illegal/unparseable PV falls back to the raw UCI string rather than
+/// failing the whole stream).
+fn pv_to_update(fen: &str, pv: &engine::PvLine) -> AnalysisUpdate {
+    let pv_uci = pv.pv.join(" ");
+    let pv_san = decode_pv_san(fen, &pv.pv).unwrap_or_else(|_| pv_uci.clone());
+
+    AnalysisUpdate {
+        depth: pv.depth,
+        seldepth: pv.seldepth,
+        nodes: pv.nodes,
+        nps: pv.nps,
+        time_ms: pv.time_ms,
+        score_cp: pv.score_cp.unwrap_or(0),
+        mate_in: pv.mate_in.unwrap_or(0),
+        is_mate: pv.mate_in.is_some(),
+        pv_uci,
+        pv_san,
     }
 }
 
-fn calculate_skill_from_elo(elo: i32) -> i32 {
-    match elo {
-        ..=1249 => 1,
-        1250..=1349 => 2,
-        1350..=1449 => 3,
-        1450..=1549 => 4,
-        1550..=1649 => 5,
-        1650..=1749 => 6,
-        1750..=1849 => 7,
-        1850..=1949 => 8,
-        1950..=2049 => 9,
-        2050..=2149 => 10,
-        2150..=2249 => 11,
-        2250..=2349 => 12,
-        2350..=2449 => 13,
-        2450..=2549 => 14,
-        2550..=2649 => 15,
-        2650..=2749 => 16,
-        2750..=2849 => 17,
-        2850..=2949 => 18,
-        2950..=3049 => 19,
-        _ => 20,
+/// Replays a UCI principal variation onto `fen` and renders it as
+/// space-separated SAN, the same way `decode_move` renders a single move.
+fn decode_pv_san(fen: &str, moves: &[String]) -> Result<String, Status> {
+    let fen: Fen = fen
+        .parse()
+        .map_err(|e| Status::invalid_argument(format!("Invalid FEN: {:?}", e)))?;
+    let mut pos: Chess = fen
+        .into_position(shakmaty::CastlingMode::Standard)
+        .map_err(|e| Status::invalid_argument(format!("Invalid position: {:?}", e)))?;
+
+    let mut sans = Vec::with_capacity(moves.len());
+    for uci in moves {
+        let uci_move: UciMove = uci
+            .parse()
+            .map_err(|e| Status::internal(format!("Invalid UCI move: {:?}", e)))?;
+        let chess_move = uci_move
+            .to_move(&pos)
+            .map_err(|e| Status::internal(format!("Illegal move: {:?}", e)))?;
+
+        sans.push(San::from_move(&pos, chess_move.clone()).to_string());
+        pos = pos
+            .play(chess_move)
+            .map_err(|e| Status::internal(format!("Illegal move: {:?}", e)))?;
     }
+
+    Ok(sans.join(" "))
+}
+
+/// Decoded move info shared by `GetBestMove` and `AnalyzePosition`: same
+/// square/piece/SAN breakdown the client already relies on for a single move.
+struct DecodedMove {
+    from: String,
+    to: String,
+    piece: String,
+    captured: Option<String>,
+    promotion: Option<String>,
+    san: String,
 }
 
-fn calculate_depth_from_elo(elo: i32) -> u8 {
-    match elo {
-        ..=1249 => 1,
-        1250..=1349 => 2,
-        1350..=1449 => 3,
-        1450..=1549 => 4,
-        1550..=1649 => 5,
-        1650..=1749 => 6,
-        1750..=1849 => 7,
-        1850..=1949 => 8,
-        1950..=2049 => 9,
-        2050..=2149 => 10,
-        2150..=2249 => 11,
-        2250..=2349 => 12,
-        2350..=2449 => 13,
-        2450..=2549 => 14,
-        2550..=2649 => 15,
-        2650..=2749 => 16,
-        2750..=2849 => 17,
-        2850..=2949 => 18,
-        2950..=3049 => 19,
-        _ => 20,
+/// Builds the search budget for a `GetBestMove` call: an explicit
+/// `movetime_ms` wins, then an explicit clock, then the strength-derived
+/// default (depth cap for `Skill`, fixed movetime for `EloLimit`).
+fn search_control_from_request(req: &PositionRequest, strength: &strength::Strength) -> strength::SearchControl {
+    if let Some(movetime_ms) = req.movetime_ms {
+        return strength::SearchControl::Movetime(movetime_ms);
+    }
+    if req.wtime_ms.is_some() || req.btime_ms.is_some() {
+        return strength::SearchControl::Clock {
+            wtime_ms: req.wtime_ms.unwrap_or(0),
+            btime_ms: req.btime_ms.unwrap_or(0),
+            winc_ms: req.winc_ms.unwrap_or(0),
+            binc_ms: req.binc_ms.unwrap_or(0),
+            moves_to_go: req.moves_to_go,
+        };
     }
+    strength::default_search_control(req.elo_rating, strength)
+}
+
+/// Parses a UCI move against the given FEN and returns its square/piece
+/// breakdown plus SAN, the same decoding `GetBestMove` has always done.
+fn decode_move(fen: &str, uci_move_str: &str) -> Result<DecodedMove, Status> {
+    let fen: Fen = fen.parse().map_err(|e| {
+        metrics::FEN_ERRORS.inc();
+        Status::invalid_argument(format!("Invalid FEN: {:?}", e))
+    })?;
+
+    let pos: Chess = fen.into_position(shakmaty::CastlingMode::Standard).map_err(|e| {
+        metrics::FEN_ERRORS.inc();
+        Status::invalid_argument(format!("Invalid position: {:?}", e))
+    })?;
+
+    let uci_move: UciMove = uci_move_str.parse().map_err(|e| {
+        metrics::ILLEGAL_MOVE_ERRORS.inc();
+        Status::internal(format!("Invalid UCI move: {:?}", e))
+    })?;
+
+    let chess_move = uci_move.to_move(&pos).map_err(|e| {
+        metrics::ILLEGAL_MOVE_ERRORS.inc();
+        Status::internal(format!("Illegal move: {:?}", e))
+    })?;
+
+    let (from, to, piece, captured, promotion) = match &chess_move {
+        shakmaty::Move::Normal {
+            role,
+            from,
+            to,
+            capture,
+            promotion,
+        } => {
+            let piece_name = format!("{:?}", role);
+            let captured_name = capture.map(|c| format!("{:?}", c));
+            let promotion_name = promotion.map(|p| format!("{:?}", p));
+
+            (
+                from.to_string(),
+                to.to_string(),
+                piece_name,
+                captured_name,
+                promotion_name,
+            )
+        }
+        shakmaty::Move::Castle { king, rook } => {
+            use shakmaty::{File, Square};
+
+            let king_to = if rook.file() == File::A {
+                Square::from_coords(File::C, king.rank())
+            } else {
+                Square::from_coords(File::G, king.rank())
+            };
+
+            (
+                king.to_string(),
+                king_to.to_string(),
+                "King".to_string(),
+                None,
+                None,
+            )
+        }
+        shakmaty::Move::EnPassant { from, to } => (
+            from.to_string(),
+            to.to_string(),
+            "Pawn".to_string(),
+            Some("Pawn".to_string()),
+            None,
+        ),
+        shakmaty::Move::Put { .. } => {
+            return Err(Status::internal("Put move not supported"));
+        }
+    };
+
+    let san = San::from_move(&pos, chess_move).to_string();
+
+    Ok(DecodedMove {
+        from,
+        to,
+        piece,
+        captured,
+        promotion,
+        san,
+    })
 }
 
 #[tokio::main]
@@ -225,7 +397,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("🔧 Creating Stockfish pool...");
 
-    let manager = StockfishManager::new(stockfish_path);
+    let manager = StockfishManager::new(stockfish_path, config.engine_tuning);
     let pool = Pool::builder(manager)
         .max_size(num_cpus::get() as usize) 
         .build()
@@ -233,21 +405,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("✅ Stockfish pool created with {} instances", num_cpus::get());
 
-    let bot_service = ChessBotService { pool };
-
-    let host = &config.server_host;
-    let port: u16 = config.server_port;
-    let addr = SocketAddr::new(host.parse()?, port);
-
-    println!(
-        "Chess Bot gRPC Server listening on {}",
-        config.server_address()
-    );
-
-    Server::builder()
-        .add_service(ChessBotServer::new(bot_service))
-        .serve(addr)
-        .await?;
+    let admin_addr: SocketAddr = config.admin_address().parse()?;
+    let admin_pool = pool.clone();
+    tokio::spawn(async move {
+        if let Err(e) = admin::serve(admin_pool, admin_addr).await {
+            eprintln!("❌ Admin listener crashed: {}", e);
+        }
+    });
+
+    match config.run_mode {
+        config::RunMode::Lichess => {
+            lichess::run(pool, &config.lichess).await?;
+        }
+        config::RunMode::Grpc => {
+            let bot_service = ChessBotService { pool };
+
+            let host = &config.server_host;
+            let port: u16 = config.server_port;
+            let addr = SocketAddr::new(host.parse()?, port);
+
+            println!(
+                "Chess Bot gRPC Server listening on {}",
+                config.server_address()
+            );
+
+            Server::builder()
+                .add_service(ChessBotServer::new(bot_service))
+                .serve(addr)
+                .await?;
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,107 @@
+//! Prometheus metrics for the admin `/metrics` endpoint. Counters/histograms
+//! are process-global `Lazy` statics so any module (the gRPC service, the
+//! pool manager) can record against them without threading a registry
+//! handle everywhere.
+
+use once_cell::sync::Lazy;
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, IntGauge, Opts, Registry, TextEncoder};
+
+pub static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+pub static GET_BEST_MOVE_REQUESTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_counter(
+        "chess_bot_get_best_move_requests_total",
+        "Total GetBestMove requests received",
+    )
+});
+
+pub static GET_BEST_MOVE_LATENCY: Lazy<Histogram> = Lazy::new(|| {
+    let histogram = Histogram::with_opts(HistogramOpts::new(
+        "chess_bot_get_best_move_latency_seconds",
+        "GetBestMove latency in seconds",
+    ))
+    .expect("valid histogram opts");
+    REGISTRY
+        .register(Box::new(histogram.clone()))
+        .expect("metric name collision");
+    histogram
+});
+
+pub static ENGINE_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
+    register_counter("chess_bot_engine_errors_total", "Stockfish engine errors during a search")
+});
+
+pub static FEN_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
+    register_counter("chess_bot_fen_errors_total", "Requests rejected for an invalid FEN")
+});
+
+pub static ILLEGAL_MOVE_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
+    register_counter(
+        "chess_bot_illegal_move_errors_total",
+        "Engine moves that were illegal in the requested position",
+    )
+});
+
+pub static ENGINE_SPAWN_FAILURES: Lazy<IntCounter> = Lazy::new(|| {
+    register_counter(
+        "chess_bot_engine_spawn_failures_total",
+        "Failures spawning a new Stockfish process for the pool",
+    )
+});
+
+pub static ENGINE_RECYCLE_FAILURES: Lazy<IntCounter> = Lazy::new(|| {
+    register_counter(
+        "chess_bot_engine_recycle_failures_total",
+        "Failures recycling a pooled Stockfish process between games",
+    )
+});
+
+pub static POOL_SIZE: Lazy<IntGauge> =
+    Lazy::new(|| register_gauge("chess_bot_pool_size", "Stockfish instances currently in the pool, idle or checked out"));
+
+pub static POOL_AVAILABLE: Lazy<IntGauge> = Lazy::new(|| {
+    register_gauge(
+        "chess_bot_pool_available",
+        "Idle Stockfish instances currently available in the pool",
+    )
+});
+
+pub static POOL_IN_USE: Lazy<IntGauge> = Lazy::new(|| {
+    register_gauge(
+        "chess_bot_pool_in_use",
+        "Stockfish instances currently checked out of the pool",
+    )
+});
+
+pub static POOL_WAITING: Lazy<IntGauge> = Lazy::new(|| {
+    register_gauge(
+        "chess_bot_pool_waiting",
+        "Callers currently waiting for a Stockfish instance",
+    )
+});
+
+fn register_counter(name: &str, help: &str) -> IntCounter {
+    let counter = IntCounter::with_opts(Opts::new(name, help)).expect("valid counter opts");
+    REGISTRY
+        .register(Box::new(counter.clone()))
+        .expect("metric name collision");
+    counter
+}
+
+fn register_gauge(name: &str, help: &str) -> IntGauge {
+    let gauge = IntGauge::with_opts(Opts::new(name, help)).expect("valid gauge opts");
+    REGISTRY
+        .register(Box::new(gauge.clone()))
+        .expect("metric name collision");
+    gauge
+}
+
+/// Renders all registered metrics in Prometheus text exposition format.
+pub fn encode() -> String {
+    let metric_families = REGISTRY.gather();
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&metric_families, &mut buffer)
+        .expect("metrics always encode");
+    String::from_utf8(buffer).expect("prometheus output is valid utf8")
+}
@@ -1,33 +1,51 @@
-use stockfish::Stockfish;
+use crate::config::EngineTuning;
+use crate::engine::StockfishEngine;
+use crate::metrics;
 use deadpool::managed::{Manager, Metrics, RecycleResult};
 use std::io;
 use std::future::Future;
 
 pub struct StockfishManager {
     path: String,
+    tuning: EngineTuning,
 }
 
 impl StockfishManager {
-    pub fn new(path: String) -> Self {
-        Self { path }
+    pub fn new(path: String, tuning: EngineTuning) -> Self {
+        Self { path, tuning }
     }
 }
 
 impl Manager for StockfishManager {
-    type Type = Stockfish;
+    type Type = StockfishEngine;
     type Error = io::Error;
 
     fn create(&self) -> impl Future<Output = Result<Self::Type, Self::Error>> + Send {
         let path = self.path.clone();
-        
+        let tuning = self.tuning;
+
         async move {
-            tokio::task::spawn_blocking(move || {
-                Stockfish::new(&path).map_err(|e| {
+            let result = tokio::task::spawn_blocking(move || {
+                let mut engine = StockfishEngine::new(&path).map_err(|e| {
                     io::Error::new(io::ErrorKind::Other, format!("Failed to create Stockfish: {}", e))
-                })
+                })?;
+
+                engine
+                    .set_option("Threads", &tuning.threads.to_string())
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Threads setup error: {}", e)))?;
+                engine
+                    .set_option("Hash", &tuning.hash_mb.to_string())
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Hash setup error: {}", e)))?;
+
+                Ok(engine)
             })
             .await
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Spawn error: {}", e)))?
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Spawn error: {}", e)))?;
+
+            if result.is_err() {
+                metrics::ENGINE_SPAWN_FAILURES.inc();
+            }
+            result
         }
     }
 
@@ -37,9 +55,11 @@ impl Manager for StockfishManager {
         _metrics: &Metrics,
     ) -> impl Future<Output = RecycleResult<Self::Error>> + Send {
         async move {
-            obj.setup_for_new_game()
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Recycle error: {}", e)))?;
-            
+            obj.setup_for_new_game().map_err(|e| {
+                metrics::ENGINE_RECYCLE_FAILURES.inc();
+                io::Error::new(io::ErrorKind::Other, format!("Recycle error: {}", e))
+            })?;
+
             Ok(())
         }
     }
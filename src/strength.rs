@@ -0,0 +1,178 @@
+//! Translates a requested ELO rating into engine configuration. Shared by
+//! the gRPC service and the Lichess bot so both pick moves the same way.
+
+use crate::chess_bot::StrengthMode;
+use crate::engine::{PvLine, StockfishEngine};
+use std::io;
+
+/// Lower bound of Stockfish's calibrated `UCI_Elo` range.
+pub const ELO_LIMIT_MIN: i32 = 1320;
+/// Upper bound of Stockfish's calibrated `UCI_Elo` range.
+pub const ELO_LIMIT_MAX: i32 = 3190;
+/// Thinking time given to an `UCI_Elo`-limited search when the caller sends
+/// no explicit `movetime`/clock (see `default_search_control`).
+pub const ELO_LIMIT_MOVETIME_MS: u32 = 1000;
+
+#[derive(Debug)]
+pub enum Strength {
+    Skill { skill_level: i32 },
+    EloLimit { uci_elo: i32 },
+}
+
+/// How the engine should budget its search, independent of `Strength`
+/// (which only configures playing strength). Callers without an explicit
+/// clock/movetime fall back to `default_search_control`.
+#[derive(Debug)]
+pub enum SearchControl {
+    Depth(u32),
+    Movetime(u32),
+    Clock {
+        wtime_ms: u32,
+        btime_ms: u32,
+        winc_ms: u32,
+        binc_ms: u32,
+        moves_to_go: Option<u32>,
+    },
+}
+
+/// Picks how to configure the engine for a requested ELO, honoring
+/// `strength_mode`. `EloLimit` only works within Stockfish's calibrated
+/// range, so out-of-range ratings fall back to the `Skill` heuristic.
+pub fn resolve_strength(elo: i32, mode: StrengthMode) -> Strength {
+    match mode {
+        StrengthMode::EloLimit if (ELO_LIMIT_MIN..=ELO_LIMIT_MAX).contains(&elo) => {
+            Strength::EloLimit { uci_elo: elo }
+        }
+        _ => Strength::Skill {
+            skill_level: calculate_skill_from_elo(elo),
+        },
+    }
+}
+
+/// The search budget to use when a caller doesn't supply an explicit
+/// `movetime`/clock: a depth cap for `Skill` play, a fixed thinking time for
+/// `EloLimit` play (a depth cap would make it look blind rather than weak).
+pub fn default_search_control(elo: i32, strength: &Strength) -> SearchControl {
+    match strength {
+        Strength::Skill { .. } => SearchControl::Depth(calculate_depth_from_elo(elo) as u32),
+        Strength::EloLimit { .. } => SearchControl::Movetime(ELO_LIMIT_MOVETIME_MS),
+    }
+}
+
+/// Applies `strength` and `control` to `engine` and searches `fen`,
+/// returning the best line found. Shared by `GetBestMove` and the Lichess
+/// bot so both play at the same strength for a given rating.
+pub fn pick_move(
+    engine: &mut StockfishEngine,
+    fen: &str,
+    strength: &Strength,
+    control: &SearchControl,
+) -> io::Result<PvLine> {
+    match strength {
+        Strength::Skill { skill_level } => {
+            engine.set_option("UCI_LimitStrength", "false")?;
+            engine.set_option("Skill Level", &skill_level.to_string())?;
+        }
+        Strength::EloLimit { uci_elo } => {
+            engine.set_option("UCI_LimitStrength", "true")?;
+            engine.set_option("UCI_Elo", &uci_elo.to_string())?;
+        }
+    }
+    engine.set_fen_position(fen)?;
+
+    match *control {
+        SearchControl::Depth(depth) => engine.go_best_move(depth),
+        SearchControl::Movetime(movetime_ms) => engine.go_movetime(movetime_ms),
+        SearchControl::Clock {
+            wtime_ms,
+            btime_ms,
+            winc_ms,
+            binc_ms,
+            moves_to_go,
+        } => engine.go_clock(wtime_ms, btime_ms, winc_ms, binc_ms, moves_to_go),
+    }
+}
+
+pub fn calculate_skill_from_elo(elo: i32) -> i32 {
+    match elo {
+        ..=1249 => 1,
+        1250..=1349 => 2,
+        1350..=1449 => 3,
+        1450..=1549 => 4,
+        1550..=1649 => 5,
+        1650..=1749 => 6,
+        1750..=1849 => 7,
+        1850..=1949 => 8,
+        1950..=2049 => 9,
+        2050..=2149 => 10,
+        2150..=2249 => 11,
+        2250..=2349 => 12,
+        2350..=2449 => 13,
+        2450..=2549 => 14,
+        2550..=2649 => 15,
+        2650..=2749 => 16,
+        2750..=2849 => 17,
+        2850..=2949 => 18,
+        2950..=3049 => 19,
+        _ => 20,
+    }
+}
+
+pub fn calculate_depth_from_elo(elo: i32) -> u8 {
+    match elo {
+        ..=1249 => 1,
+        1250..=1349 => 2,
+        1350..=1449 => 3,
+        1450..=1549 => 4,
+        1550..=1649 => 5,
+        1650..=1749 => 6,
+        1750..=1849 => 7,
+        1850..=1949 => 8,
+        1950..=2049 => 9,
+        2050..=2149 => 10,
+        2150..=2249 => 11,
+        2250..=2349 => 12,
+        2350..=2449 => 13,
+        2450..=2549 => 14,
+        2550..=2649 => 15,
+        2650..=2749 => 16,
+        2750..=2849 => 17,
+        2850..=2949 => 18,
+        2950..=3049 => 19,
+        _ => 20,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skill_from_elo_covers_the_full_range() {
+        assert_eq!(calculate_skill_from_elo(800), 1);
+        assert_eq!(calculate_skill_from_elo(1250), 2);
+        assert_eq!(calculate_skill_from_elo(2000), 9);
+        assert_eq!(calculate_skill_from_elo(3200), 20);
+    }
+
+    #[test]
+    fn resolve_strength_uses_elo_limit_within_calibrated_range() {
+        let strength = resolve_strength(2000, StrengthMode::EloLimit);
+        assert!(matches!(strength, Strength::EloLimit { uci_elo: 2000 }));
+    }
+
+    #[test]
+    fn resolve_strength_falls_back_to_skill_outside_calibrated_range() {
+        let strength = resolve_strength(800, StrengthMode::EloLimit);
+        assert!(matches!(strength, Strength::Skill { skill_level: 1 }));
+
+        let strength = resolve_strength(3200, StrengthMode::EloLimit);
+        assert!(matches!(strength, Strength::Skill { skill_level: 20 }));
+    }
+
+    #[test]
+    fn resolve_strength_uses_skill_when_requested() {
+        let strength = resolve_strength(2000, StrengthMode::Skill);
+        assert!(matches!(strength, Strength::Skill { skill_level: 9 }));
+    }
+}